@@ -0,0 +1,91 @@
+use base64::Engine;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A single `~/.config/req/credentials.toml` entry. Exactly one of these is
+/// expected to be set per host; if more than one is, `bearer` wins, then
+/// `basic`, then `header`.
+#[derive(Debug, Deserialize)]
+struct CredentialEntry {
+    bearer: Option<String>,
+    basic: Option<String>,
+    header: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CredentialsFile {
+    #[serde(flatten)]
+    hosts: HashMap<String, CredentialEntry>,
+}
+
+pub fn config_path() -> PathBuf {
+    let mut dir = dirs::config_dir().unwrap_or_else(std::env::temp_dir);
+    dir.push("req");
+    dir.push("credentials.toml");
+    dir
+}
+
+/// Resolves the `Authorization` header value for `host` out of an already-read
+/// `credentials.toml`. Split out from `lookup` so the precedence rules are
+/// testable without touching the filesystem.
+fn resolve(host: &str, contents: &str) -> Option<String> {
+    let file: CredentialsFile = toml::from_str(contents).ok()?;
+    let entry = file.hosts.get(host)?;
+    if let Some(token) = &entry.bearer {
+        return Some(format!("Bearer {}", token));
+    }
+    if let Some(user_pass) = &entry.basic {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(user_pass);
+        return Some(format!("Basic {}", encoded));
+    }
+    entry.header.clone()
+}
+
+/// Looks up the `Authorization` header value to attach for `host`, reading
+/// `~/.config/req/credentials.toml`. Returns `None` if the file is missing,
+/// unparseable, or has no entry for `host`.
+pub fn lookup(host: &str) -> Option<String> {
+    let contents = std::fs::read_to_string(config_path()).ok()?;
+    resolve(host, &contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve;
+
+    #[test]
+    fn test_resolve_bearer() {
+        let toml = "[api.example.com]\nbearer = \"abc123\"\n";
+        assert_eq!(resolve("api.example.com", toml), Some("Bearer abc123".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_basic() {
+        let toml = "[api.example.com]\nbasic = \"user:pass\"\n";
+        assert_eq!(resolve("api.example.com", toml), Some("Basic dXNlcjpwYXNz".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_header() {
+        let toml = "[api.example.com]\nheader = \"Custom xyz\"\n";
+        assert_eq!(resolve("api.example.com", toml), Some("Custom xyz".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_bearer_takes_precedence_over_basic() {
+        let toml = "[api.example.com]\nbearer = \"abc123\"\nbasic = \"user:pass\"\n";
+        assert_eq!(resolve("api.example.com", toml), Some("Bearer abc123".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_no_entry_for_host() {
+        let toml = "[api.example.com]\nbearer = \"abc123\"\n";
+        assert_eq!(resolve("other.example.com", toml), None);
+    }
+
+    #[test]
+    fn test_resolve_unparseable_file() {
+        assert_eq!(resolve("api.example.com", "not valid toml {{"), None);
+    }
+}
@@ -1,15 +1,16 @@
+mod credentials;
 mod middleware;
 
 use std::borrow::Cow;
 use clap::{Parser};
 use colored::Colorize;
-use httpclient::middleware::{FollowRedirectsMiddleware};
 use httpclient::{InMemoryBody};
 use std::{fs};
 use std::str::FromStr;
 use base64::Engine;
 use colored_json::ToColoredJson;
-use middleware::VerboseMiddleware;
+use middleware::{FollowRedirectsMiddleware, VerboseMiddleware};
+use rand::Rng;
 
 static EXAMPLES: &[(&'static str, &'static str)] = &[
     ("Plain GET request", "req jsonip.com"),
@@ -25,6 +26,26 @@ static EXAMPLES: &[(&'static str, &'static str)] = &[
         "Sends a JSON POST request with URL params. URL params before --json, JSON body after --json.",
         "req localhost:5000/search cache=0 --json query='search query'",
     ),
+    (
+        "Signs the request with OAuth 1.0a.",
+        "req api.twitter.com/1.1/statuses/update.json --oauth1 consumer_key:consumer_secret --oauth1-token token:token_secret",
+    ),
+    (
+        "Caches the response on disk, honoring Cache-Control on subsequent calls.",
+        "req jsonip.com --cache",
+    ),
+    (
+        "Skips the ~/.config/req/credentials.toml lookup for this call.",
+        "req api.example.com/me --no-auto-auth",
+    ),
+    (
+        "Uploads a file alongside form fields as multipart/form-data.",
+        "req localhost:5000/upload --multipart avatar=@pic.png name=me",
+    ),
+    (
+        "Retries the request up to 3 times on connection errors or 408/429/500/502/503/504 responses.",
+        "req flaky-api.example.com --retry 3",
+    ),
 ];
 
 
@@ -92,6 +113,27 @@ struct Cli {
 
     #[arg(long)]
     file: Option<String>,
+
+    #[arg(long, num_args = 1.., help = "Sets a multipart/form-data body. --multipart is greedy, so every value after it is treated as a multipart field. Use `key=value` for a text field, or `key=@path` to attach a file, with its content type auto-detected.")]
+    multipart: Option<Vec<String>>,
+
+    #[arg(long, help = "Sign the request with OAuth 1.0a, using `consumer_key:consumer_secret`.")]
+    oauth1: Option<String>,
+
+    #[arg(long, requires = "oauth1", help = "Sets the OAuth 1.0a access token, as `token:token_secret`. Used together with --oauth1.")]
+    oauth1_token: Option<String>,
+
+    #[arg(long, help = "Cache responses on disk and revalidate per Cache-Control, so repeated calls to the same endpoint are fast and bandwidth-light.")]
+    cache: bool,
+
+    #[arg(long, help = "Disable looking up a matching host in ~/.config/req/credentials.toml to set Authorization automatically.")]
+    no_auto_auth: bool,
+
+    #[arg(long, help = "Retry the request up to N times on connection errors or retriable status codes (408, 429, 500, 502, 503, 504), with exponential backoff.")]
+    retry: Option<u32>,
+
+    #[arg(long, requires = "retry", help = "Also retry POST requests. By default only idempotent methods (GET, HEAD, PUT, DELETE, OPTIONS) are retried.")]
+    retry_all_methods: bool,
 }
 
 
@@ -124,6 +166,39 @@ fn build_map<'a>(values: impl Iterator<Item=&'a str>) -> serde_json::Value {
     serde_json::Value::Object(map)
 }
 
+/// Builds a `multipart/form-data` body from `key=value` text fields and
+/// `key=@path` file fields, returning the boundary and the encoded body.
+fn build_multipart<'a>(values: impl Iterator<Item=&'a str>) -> (String, Vec<u8>) {
+    let boundary: String = rand::rng()
+        .sample_iter(rand::distr::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+
+    let mut body = Vec::new();
+    for pair in values {
+        let (key, value) = split_pair(pair, &['=']).expect("Multipart fields must be in the form of key=value or key=@path");
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        if let Some(path) = value.strip_prefix('@') {
+            let filename = std::path::Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or(path);
+            let content_type = mime_guess::from_path(path).first_or_octet_stream();
+            let contents = fs::read(path).expect("Failed to read file.");
+            body.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                    key, filename, content_type,
+                ).as_bytes(),
+            );
+            body.extend_from_slice(&contents);
+        } else {
+            body.extend_from_slice(format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n{}", key, value).as_bytes());
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    (boundary, body)
+}
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     sigpipe::reset();
@@ -168,11 +243,22 @@ async fn main() {
         headers.push(("Cookie", Cow::Owned(cli.cookies.join("; "))));
     }
 
+    // Auto-auth: attach a matching credential from ~/.config/req/credentials.toml
+    // unless a CLI flag already set Authorization explicitly.
+    if !cli.no_auto_auth && !headers.iter().any(|(h, _)| h.to_lowercase() == "authorization") {
+        let host = httpclient::Uri::from_str(&url).ok().and_then(|u| u.host().map(String::from));
+        if let Some(host) = host {
+            if let Some(auth) = credentials::lookup(&host) {
+                headers.push(("Authorization", Cow::Owned(auth)));
+            }
+        }
+    }
+
     // Set method
     let method = cli.method
         .map(|v| httpclient::Method::from_str(&v.to_uppercase()).expect("Method must be one of: GET, POST, PUT, DELETE, PATCH, HEAD, OPTIONS, TRACE, CONNECT"))
         .unwrap_or_else(|| {
-            if cli.json.is_some() || cli.form.is_some() {
+            if cli.json.is_some() || cli.form.is_some() || cli.multipart.is_some() {
                 httpclient::Method::POST
             } else {
                 httpclient::Method::GET
@@ -182,13 +268,38 @@ async fn main() {
     let mut client = httpclient::Client::new();
 
     if !cli.no_follow {
-        client = client.with_middleware(FollowRedirectsMiddleware {});
+        client = client.with_middleware(FollowRedirectsMiddleware::default());
     }
 
     if cli.verbose {
         client = client.with_middleware(VerboseMiddleware {});
     }
 
+    if cli.cache {
+        client = client.with_middleware(middleware::CacheMiddleware {});
+    }
+
+    if let Some(oauth1) = cli.oauth1 {
+        let (consumer_key, consumer_secret) = split_pair(&oauth1, &[':']).expect("--oauth1 must be in the form of consumer_key:consumer_secret");
+        let (token, token_secret) = cli.oauth1_token
+            .as_deref()
+            .map(|v| split_pair(v, &[':']).expect("--oauth1-token must be in the form of token:token_secret"))
+            .unzip();
+        client = client.with_middleware(middleware::OAuth1Middleware {
+            consumer_key: consumer_key.to_string(),
+            consumer_secret: consumer_secret.to_string(),
+            token: token.map(|v| v.to_string()),
+            token_secret: token_secret.map(|v| v.to_string()),
+        });
+    }
+
+    if let Some(retry) = cli.retry {
+        client = client.with_middleware(middleware::RetryMiddleware {
+            max_retries: retry,
+            retry_all_methods: cli.retry_all_methods,
+        });
+    }
+
     let mut builder = client.request(method.clone(), &url);
 
     // Set params
@@ -220,6 +331,13 @@ async fn main() {
         builder = builder.body(InMemoryBody::Bytes(file));
     }
 
+    // Set multipart
+    if let Some(multipart) = cli.multipart {
+        let (boundary, body) = build_multipart(multipart.iter().map(|s| s.as_str()));
+        builder = builder.body(InMemoryBody::Bytes(body));
+        headers.push(("Content-Type", Cow::Owned(format!("multipart/form-data; boundary={}", boundary))));
+    }
+
     // Add headers
     builder = builder.headers(headers.clone().iter().map(|(k, v)| (*k, v.as_ref())));
 
@@ -263,7 +381,7 @@ async fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::build_map;
+    use crate::{build_map, build_multipart};
 
     #[test]
     fn test_build_map() {
@@ -292,4 +410,14 @@ mod tests {
         assert_eq!(result["d"], 5);
         assert_eq!(result["e"], -5.5);
     }
+
+    #[test]
+    fn test_build_multipart_text_field() {
+        let v = vec!["name=me"];
+        let (boundary, body) = build_multipart(v.into_iter());
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains(&format!("--{}\r\n", boundary)));
+        assert!(body.contains("Content-Disposition: form-data; name=\"name\"\r\n\r\nme"));
+        assert!(body.ends_with(&format!("--{}--\r\n", boundary)));
+    }
 }
@@ -1,6 +1,13 @@
 use async_trait::async_trait;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
 use httpclient::{InMemoryBody, InMemoryRequest, Middleware, ProtocolResult, Response};
 use httpclient::middleware::Next;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug)]
 pub struct VerboseMiddleware;
@@ -41,3 +48,535 @@ impl Middleware for VerboseMiddleware {
         res
     }
 }
+
+/// Percent-encodes per RFC 3986, leaving only unreserved characters (`A-Za-z0-9-._~`) untouched.
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// Signs outgoing requests with the OAuth 1.0a HMAC-SHA1 scheme.
+#[derive(Debug, Clone)]
+pub struct OAuth1Middleware {
+    pub consumer_key: String,
+    pub consumer_secret: String,
+    pub token: Option<String>,
+    pub token_secret: Option<String>,
+}
+
+impl OAuth1Middleware {
+    fn nonce() -> String {
+        rand::rng()
+            .sample_iter(rand::distr::Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect()
+    }
+
+    fn timestamp() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).expect("System clock is before UNIX_EPOCH").as_secs()
+    }
+
+    fn query_params(uri: &httpclient::Uri) -> Vec<(String, String)> {
+        uri.query()
+            .map(|q| serde_urlencoded::from_str::<Vec<(String, String)>>(q).unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    fn form_params(body: &InMemoryBody) -> Vec<(String, String)> {
+        match body {
+            InMemoryBody::Text(s) => serde_urlencoded::from_str::<Vec<(String, String)>>(s).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn base_url(uri: &httpclient::Uri) -> String {
+        format!("{}://{}{}", uri.scheme_str().unwrap_or("http"), uri.host().unwrap_or(""), uri.path())
+    }
+
+    fn signature(&self, method: &str, base_url: &str, params: &[(String, String)]) -> String {
+        let mut encoded_pairs: Vec<String> = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+            .collect();
+        encoded_pairs.sort();
+        let param_string = encoded_pairs.join("&");
+
+        let base_string = format!("{}&{}&{}", method, percent_encode(base_url), percent_encode(&param_string));
+
+        let signing_key = format!(
+            "{}&{}",
+            percent_encode(&self.consumer_secret),
+            percent_encode(self.token_secret.as_deref().unwrap_or("")),
+        );
+
+        let mut mac = Hmac::<Sha1>::new_from_slice(signing_key.as_bytes()).expect("HMAC accepts a key of any size");
+        mac.update(base_string.as_bytes());
+        base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+    }
+}
+
+#[async_trait]
+impl Middleware for OAuth1Middleware {
+    async fn handle(&self, mut request: InMemoryRequest, next: Next<'_>) -> ProtocolResult<Response> {
+        let method = request.method().to_string().to_uppercase();
+        let base_url = Self::base_url(request.uri());
+
+        let mut params = Self::query_params(request.uri());
+        params.extend(Self::form_params(request.body()));
+
+        let nonce = Self::nonce();
+        let timestamp = Self::timestamp().to_string();
+
+        params.push(("oauth_consumer_key".to_string(), self.consumer_key.clone()));
+        params.push(("oauth_nonce".to_string(), nonce.clone()));
+        params.push(("oauth_signature_method".to_string(), "HMAC-SHA1".to_string()));
+        params.push(("oauth_timestamp".to_string(), timestamp.clone()));
+        if let Some(token) = &self.token {
+            params.push(("oauth_token".to_string(), token.clone()));
+        }
+        params.push(("oauth_version".to_string(), "1.0".to_string()));
+
+        let signature = self.signature(&method, &base_url, &params);
+
+        let mut header_parts = vec![
+            format!(r#"oauth_consumer_key="{}""#, percent_encode(&self.consumer_key)),
+            format!(r#"oauth_nonce="{}""#, percent_encode(&nonce)),
+            format!(r#"oauth_signature="{}""#, percent_encode(&signature)),
+            r#"oauth_signature_method="HMAC-SHA1""#.to_string(),
+            format!(r#"oauth_timestamp="{}""#, timestamp),
+            r#"oauth_version="1.0""#.to_string(),
+        ];
+        if let Some(token) = &self.token {
+            header_parts.push(format!(r#"oauth_token="{}""#, percent_encode(token)));
+        }
+        header_parts.sort();
+
+        request.headers_mut().insert("Authorization", format!("OAuth {}", header_parts.join(", ")).parse().unwrap());
+
+        next.run(request).await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    stored_at: u64,
+    max_age: Option<u64>,
+    no_cache: bool,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Base64-encoded response body, so binary bodies round-trip exactly.
+    body: String,
+}
+
+/// Parses a `Cache-Control` header value into `(no_store, no_cache, max_age)`.
+fn parse_cache_control(value: &str) -> (bool, bool, Option<u64>) {
+    let mut no_store = false;
+    let mut no_cache = false;
+    let mut max_age = None;
+    for directive in value.split(',') {
+        let directive = directive.trim();
+        if directive.eq_ignore_ascii_case("no-store") {
+            no_store = true;
+        } else if directive.eq_ignore_ascii_case("no-cache") {
+            no_cache = true;
+        } else if let Some(seconds) = directive.strip_prefix("max-age=") {
+            max_age = seconds.trim().parse().ok();
+        }
+    }
+    (no_store, no_cache, max_age)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("System clock is before UNIX_EPOCH").as_secs()
+}
+
+/// Caches responses on disk under `~/.cache/req`, revalidated per `Cache-Control`.
+#[derive(Debug)]
+pub struct CacheMiddleware;
+
+impl CacheMiddleware {
+    fn cache_dir() -> PathBuf {
+        let mut dir = dirs::cache_dir().unwrap_or_else(std::env::temp_dir);
+        dir.push("req");
+        dir
+    }
+
+    fn cache_path(method: &str, url: &str) -> PathBuf {
+        let mut hasher = Sha1::new();
+        hasher.update(method.as_bytes());
+        hasher.update(b" ");
+        hasher.update(url.as_bytes());
+        let hex = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        Self::cache_dir().join(hex)
+    }
+
+    fn load(path: &Path) -> Option<CacheEntry> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    fn store(path: &Path, entry: &CacheEntry) {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(contents) = serde_json::to_string(entry) {
+            let _ = std::fs::write(path, contents);
+        }
+    }
+
+    fn entry_response(entry: &CacheEntry) -> Response {
+        let bytes = base64::engine::general_purpose::STANDARD.decode(&entry.body).unwrap_or_default();
+        let mut builder = http::Response::builder().status(entry.status);
+        for (name, value) in &entry.headers {
+            builder = builder.header(name, value);
+        }
+        builder.body(InMemoryBody::Bytes(bytes)).expect("Cached status, headers, and body always build a valid response")
+    }
+}
+
+#[async_trait]
+impl Middleware for CacheMiddleware {
+    async fn handle(&self, mut request: InMemoryRequest, next: Next<'_>) -> ProtocolResult<Response> {
+        let method = request.method().to_string();
+        let url = request.uri().to_string();
+        let path = Self::cache_path(&method, &url);
+        let cached = Self::load(&path);
+
+        if let Some(entry) = &cached {
+            let fresh = !entry.no_cache
+                && entry.max_age.map(|max_age| now().saturating_sub(entry.stored_at) < max_age).unwrap_or(false);
+            if fresh {
+                return Ok(Self::entry_response(entry));
+            }
+            if let Some(etag) = &entry.etag {
+                request.headers_mut().insert("If-None-Match", etag.parse().unwrap());
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request.headers_mut().insert("If-Modified-Since", last_modified.parse().unwrap());
+            }
+        }
+
+        let res = next.run(request).await?;
+
+        if res.status() == http::StatusCode::NOT_MODIFIED {
+            if let Some(mut entry) = cached {
+                entry.stored_at = now();
+                Self::store(&path, &entry);
+                return Ok(Self::entry_response(&entry));
+            }
+            return Ok(res);
+        }
+
+        let (no_store, no_cache, max_age) = res
+            .headers()
+            .get("Cache-Control")
+            .and_then(|v| v.to_str().ok())
+            .map(parse_cache_control)
+            .unwrap_or((true, false, None));
+
+        if no_store {
+            return Ok(res);
+        }
+
+        let etag = res.headers().get("ETag").and_then(|v| v.to_str().ok()).map(String::from);
+        let last_modified = res.headers().get("Last-Modified").and_then(|v| v.to_str().ok()).map(String::from);
+        let status = res.status().as_u16();
+        let headers = res
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| value.to_str().ok().map(|v| (name.to_string(), v.to_string())))
+            .collect();
+        let (parts, body) = res.into_parts();
+        let bytes = match body {
+            InMemoryBody::Text(s) => s.into_bytes(),
+            InMemoryBody::Bytes(b) => b,
+            InMemoryBody::Json(j) => serde_json::to_vec(&j).expect("Failed to serialize JSON"),
+            InMemoryBody::Empty => Vec::new(),
+        };
+        let entry = CacheEntry {
+            stored_at: now(),
+            max_age,
+            no_cache,
+            etag,
+            last_modified,
+            status,
+            headers,
+            body: base64::engine::general_purpose::STANDARD.encode(&bytes),
+        };
+        Self::store(&path, &entry);
+        Ok(http::Response::from_parts(parts, InMemoryBody::Bytes(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use httpclient::InMemoryBody;
+    use super::{CacheEntry, CacheMiddleware, origin, parse_cache_control, parse_retry_after, percent_encode, resolve_location};
+
+    #[test]
+    fn test_percent_encode_unreserved() {
+        assert_eq!(percent_encode("abcXYZ123-._~"), "abcXYZ123-._~");
+    }
+
+    #[test]
+    fn test_percent_encode_reserved() {
+        assert_eq!(percent_encode("foo bar/baz?"), "foo%20bar%2Fbaz%3F");
+    }
+
+    #[test]
+    fn test_parse_cache_control_max_age() {
+        let (no_store, no_cache, max_age) = parse_cache_control("max-age=3600, must-revalidate");
+        assert!(!no_store);
+        assert!(!no_cache);
+        assert_eq!(max_age, Some(3600));
+    }
+
+    #[test]
+    fn test_parse_cache_control_no_store() {
+        let (no_store, no_cache, max_age) = parse_cache_control("no-store");
+        assert!(no_store);
+        assert!(!no_cache);
+        assert_eq!(max_age, None);
+    }
+
+    #[test]
+    fn test_parse_cache_control_no_cache() {
+        let (no_store, no_cache, max_age) = parse_cache_control("no-cache");
+        assert!(!no_store);
+        assert!(no_cache);
+        assert_eq!(max_age, None);
+    }
+
+    #[test]
+    fn test_entry_response_restores_binary_body_and_headers() {
+        let bytes = vec![0xff, 0xfe, 0x00, 0x80];
+        let entry = CacheEntry {
+            stored_at: 0,
+            max_age: Some(60),
+            no_cache: false,
+            etag: None,
+            last_modified: None,
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "image/png".to_string())],
+            body: base64::engine::general_purpose::STANDARD.encode(&bytes),
+        };
+        let res = CacheMiddleware::entry_response(&entry);
+        assert_eq!(res.headers().get("Content-Type").unwrap(), "image/png");
+        match res.into_body() {
+            InMemoryBody::Bytes(b) => assert_eq!(b, bytes),
+            other => panic!("expected InMemoryBody::Bytes, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_retry_after_seconds() {
+        assert_eq!(parse_retry_after("120"), Some(std::time::Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn test_parse_retry_after_invalid() {
+        assert_eq!(parse_retry_after("not-a-date"), None);
+    }
+
+    #[test]
+    fn test_resolve_location_relative_without_leading_slash() {
+        let base: httpclient::Uri = "https://example.com/account/profile".parse().unwrap();
+        let resolved = resolve_location(&base, "login");
+        assert_eq!(resolved.to_string(), "https://example.com/account/login");
+    }
+
+    #[test]
+    fn test_resolve_location_absolute_path() {
+        let base: httpclient::Uri = "https://example.com/account/profile".parse().unwrap();
+        let resolved = resolve_location(&base, "/login");
+        assert_eq!(resolved.to_string(), "https://example.com/login");
+    }
+
+    #[test]
+    fn test_resolve_location_absolute_uri() {
+        let base: httpclient::Uri = "https://example.com/account/profile".parse().unwrap();
+        let resolved = resolve_location(&base, "https://other.com/landing");
+        assert_eq!(resolved.to_string(), "https://other.com/landing");
+    }
+
+    #[test]
+    fn test_origin_differs_on_scheme_downgrade() {
+        let https: httpclient::Uri = "https://example.com/a".parse().unwrap();
+        let http: httpclient::Uri = "http://example.com/a".parse().unwrap();
+        assert_ne!(origin(&https), origin(&http));
+    }
+
+    #[test]
+    fn test_origin_differs_on_port() {
+        let default_port: httpclient::Uri = "https://example.com/a".parse().unwrap();
+        let explicit_port: httpclient::Uri = "https://example.com:8443/a".parse().unwrap();
+        assert_ne!(origin(&default_port), origin(&explicit_port));
+    }
+
+    #[test]
+    fn test_origin_same_host_and_scheme() {
+        let a: httpclient::Uri = "https://example.com/a".parse().unwrap();
+        let b: httpclient::Uri = "https://example.com/b".parse().unwrap();
+        assert_eq!(origin(&a), origin(&b));
+    }
+}
+
+/// Resolves a `Location` header value against the request URI it was received
+/// in response to. `location` is most often just a path, but may be relative
+/// to the current path's directory (e.g. `login` from `/account/profile`
+/// resolves to `/account/login`), or a full absolute URI. Dot segments (`.`,
+/// `..`) are kept as they are, a query-only reference (`?q`) is appended to
+/// the current directory, and a network-path reference (`//host/path`) is
+/// taken as a path on the current host.
+fn resolve_location(request_uri: &httpclient::Uri, location: &str) -> httpclient::Uri {
+    if let Ok(absolute) = location.parse::<httpclient::Uri>() {
+        if absolute.scheme().is_some() {
+            return absolute;
+        }
+    }
+
+    let path_and_query = if location.starts_with('/') {
+        location.to_string()
+    } else {
+        let base_path = request_uri.path();
+        let base_dir = &base_path[..=base_path.rfind('/').unwrap_or(0)];
+        format!("{}{}", base_dir, location)
+    };
+
+    let mut parts = request_uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.parse().expect("Resolved redirect path must be a valid path and query"));
+    httpclient::Uri::from_parts(parts).expect("Resolved redirect URI must be valid")
+}
+
+/// The `(scheme, host, port)` a request is sent to, used to decide whether a
+/// redirect crosses an origin boundary and so must shed credentials.
+fn origin(uri: &httpclient::Uri) -> (Option<String>, Option<String>, Option<u16>) {
+    (uri.scheme_str().map(String::from), uri.host().map(String::from), uri.port_u16())
+}
+
+/// Follows redirects like the upstream middleware, but strips `Authorization`
+/// and `Cookie` headers whenever a redirect crosses to a different origin.
+#[derive(Debug)]
+pub struct FollowRedirectsMiddleware {
+    pub max_redirects: u8,
+}
+
+impl Default for FollowRedirectsMiddleware {
+    fn default() -> Self {
+        Self { max_redirects: 10 }
+    }
+}
+
+#[async_trait]
+impl Middleware for FollowRedirectsMiddleware {
+    async fn handle(&self, mut request: InMemoryRequest, next: Next<'_>) -> ProtocolResult<Response> {
+        for _ in 0..self.max_redirects {
+            let current_origin = origin(request.uri());
+            let method = request.method().clone();
+
+            let res = next.clone().run(request.clone()).await?;
+            if !res.status().is_redirection() {
+                return Ok(res);
+            }
+            let location = match res.headers().get(http::header::LOCATION).and_then(|v| v.to_str().ok()) {
+                Some(location) => location.to_string(),
+                None => return Ok(res),
+            };
+
+            let next_uri = resolve_location(request.uri(), &location);
+            let next_origin = origin(&next_uri);
+            *request.uri_mut() = next_uri;
+
+            if res.status() == http::StatusCode::SEE_OTHER || (res.status() == http::StatusCode::FOUND && method != http::Method::HEAD) {
+                *request.method_mut() = http::Method::GET;
+                *request.body_mut() = InMemoryBody::Empty;
+            }
+
+            if next_origin != current_origin {
+                request.headers_mut().remove(http::header::AUTHORIZATION);
+                request.headers_mut().remove(http::header::COOKIE);
+            }
+        }
+        next.run(request).await
+    }
+}
+
+const RETRIABLE_STATUSES: &[u16] = &[408, 429, 500, 502, 503, 504];
+const RETRY_BASE_DELAY_MS: u64 = 200;
+const RETRY_MAX_DELAY_MS: u64 = 10_000;
+
+/// Computes the exponential backoff (with jitter) delay before retry attempt
+/// `attempt` (0-indexed), doubling the base delay each attempt and capping it.
+fn retry_backoff(attempt: u32) -> std::time::Duration {
+    let exp = RETRY_BASE_DELAY_MS.saturating_mul(1u64 << attempt.min(16)).min(RETRY_MAX_DELAY_MS);
+    let jitter = rand::rng().random_range(0..=exp / 2);
+    std::time::Duration::from_millis(exp / 2 + jitter)
+}
+
+/// Parses a `Retry-After` header value, which is either a number of seconds
+/// or an HTTP-date.
+fn parse_retry_after(value: &str) -> Option<std::time::Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(std::time::Duration::from_secs(seconds));
+    }
+    let when = httpdate::parse_http_date(value.trim()).ok()?;
+    when.duration_since(SystemTime::now()).ok()
+}
+
+/// Retries idempotent requests with exponential backoff, honoring `Retry-After`.
+#[derive(Debug)]
+pub struct RetryMiddleware {
+    pub max_retries: u32,
+    pub retry_all_methods: bool,
+}
+
+impl RetryMiddleware {
+    fn is_retriable_method(&self, method: &http::Method) -> bool {
+        matches!(*method, http::Method::GET | http::Method::HEAD | http::Method::PUT | http::Method::DELETE | http::Method::OPTIONS)
+            || (self.retry_all_methods && *method == http::Method::POST)
+    }
+
+    fn should_retry(result: &ProtocolResult<Response>) -> bool {
+        match result {
+            Ok(res) => RETRIABLE_STATUSES.contains(&res.status().as_u16()),
+            Err(_) => true,
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for RetryMiddleware {
+    async fn handle(&self, request: InMemoryRequest, next: Next<'_>) -> ProtocolResult<Response> {
+        if !self.is_retriable_method(request.method()) {
+            return next.run(request).await;
+        }
+
+        let mut attempt = 0;
+        loop {
+            let result = next.clone().run(request.clone()).await;
+            if !Self::should_retry(&result) || attempt >= self.max_retries {
+                return result;
+            }
+
+            let delay = result
+                .as_ref()
+                .ok()
+                .and_then(|res| res.headers().get(http::header::RETRY_AFTER))
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after)
+                .unwrap_or_else(|| retry_backoff(attempt));
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}